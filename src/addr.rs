@@ -0,0 +1,88 @@
+//! Shared parsing for the SOCKS5 destination address format (`ATYP` plus
+//! address and port), used by the TCP request header and, via QUIC
+//! streams, the `quic` tunneled transport.
+
+use anyhow::Result;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// A parsed SOCKS5 destination.
+pub struct SocksAddr {
+    /// Wire-format ATYP (`0x01` IPv4, `0x03` domain, `0x04` IPv6).
+    pub atyp: u8,
+    /// Raw address bytes as they appeared on the wire, for forwarding
+    /// verbatim (e.g. to an upstream SOCKS5 proxy).
+    pub addr_bytes: Vec<u8>,
+    /// A `host:port` (or `[ipv6]:port`) string suitable for `TcpStream::connect`.
+    pub connect_str: String,
+    /// Plain hostname/IP string, suitable for access-rule matching.
+    pub host: String,
+    /// The resolved IP if the target was an IP literal; `None` for domains.
+    pub ip: Option<IpAddr>,
+    pub port: u16,
+}
+
+/// Read `addr PORT` from `reader`, given an already-consumed `atyp` byte.
+/// `atyp` must be one of `0x01`/`0x03`/`0x04`; callers should validate and
+/// reply with the appropriate SOCKS error themselves before calling this.
+pub async fn read_addr_port<R: AsyncRead + Unpin>(reader: &mut R, atyp: u8) -> Result<SocksAddr> {
+    match atyp {
+        0x01 => {
+            let mut addr_buf = [0u8; 4];
+            reader.read_exact(&mut addr_buf).await?;
+            let mut port_buf = [0u8; 2];
+            reader.read_exact(&mut port_buf).await?;
+            let port = u16::from_be_bytes(port_buf);
+            let ip = Ipv4Addr::from(addr_buf);
+            let s = format!("{}:{}", ip, port);
+            Ok(SocksAddr {
+                atyp,
+                addr_bytes: addr_buf.to_vec(),
+                connect_str: s,
+                host: ip.to_string(),
+                ip: Some(IpAddr::V4(ip)),
+                port,
+            })
+        }
+        0x03 => {
+            let mut len_buf = [0u8; 1];
+            reader.read_exact(&mut len_buf).await?;
+            let len = len_buf[0] as usize;
+            let mut domain_buf = vec![0u8; len];
+            reader.read_exact(&mut domain_buf).await?;
+            let domain = String::from_utf8_lossy(&domain_buf).into_owned();
+            let mut port_buf = [0u8; 2];
+            reader.read_exact(&mut port_buf).await?;
+            let port = u16::from_be_bytes(port_buf);
+            let connect_str = format!("{}:{}", domain, port);
+            let mut addr_bytes = vec![len_buf[0]];
+            addr_bytes.extend_from_slice(&domain_buf);
+            Ok(SocksAddr {
+                atyp,
+                addr_bytes,
+                connect_str,
+                host: domain,
+                ip: None,
+                port,
+            })
+        }
+        0x04 => {
+            let mut addr_buf = [0u8; 16];
+            reader.read_exact(&mut addr_buf).await?;
+            let mut port_buf = [0u8; 2];
+            reader.read_exact(&mut port_buf).await?;
+            let port = u16::from_be_bytes(port_buf);
+            let ip = Ipv6Addr::from(addr_buf);
+            let s = format!("[{}]:{}", ip, port);
+            Ok(SocksAddr {
+                atyp,
+                addr_bytes: addr_buf.to_vec(),
+                connect_str: s,
+                host: ip.to_string(),
+                ip: Some(IpAddr::V6(ip)),
+                port,
+            })
+        }
+        _ => Err(anyhow::anyhow!("Unsupported address type: {}", atyp)),
+    }
+}
@@ -0,0 +1,122 @@
+//! TOML multi-user configuration: credentials plus per-user destination
+//! access rules, loaded via `--config <file.toml>`.
+//!
+//! Example file:
+//!
+//! ```toml
+//! [[user]]
+//! username = "alice"
+//! password = "hunter2"
+//! allow = ["10.0.0.0/8", "example.com:443"]
+//!
+//! [[user]]
+//! username = "bob"
+//! password = "swordfish"
+//! deny = ["169.254.0.0/16"]
+//! ```
+
+use anyhow::{Context, Result};
+use ipnet::IpNet;
+use serde::Deserialize;
+use std::net::IpAddr;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    #[serde(rename = "user", default)]
+    pub users: Vec<UserConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserConfig {
+    pub username: String,
+    pub password: String,
+    /// Destination patterns this user may connect to. Empty means "allow
+    /// anything not explicitly denied".
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Destination patterns this user may not connect to, checked before
+    /// `allow`.
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {:?}", path))?;
+        toml::from_str(&text).with_context(|| format!("Failed to parse config file {:?}", path))
+    }
+
+    pub fn find_user(&self, username: &str) -> Option<&UserConfig> {
+        self.users.iter().find(|u| u.username == username)
+    }
+}
+
+impl UserConfig {
+    /// Whether this user is allowed to connect to `host:port`. `ip` should
+    /// be set when the destination is an IP literal (SOCKS ATYP IPv4/IPv6),
+    /// enabling CIDR-based rules; for domain-name targets it is `None` and
+    /// only hostname/wildcard rules apply.
+    pub fn is_allowed(&self, host: &str, ip: Option<IpAddr>, port: u16) -> bool {
+        if self.deny.iter().any(|rule| rule_matches(rule, host, ip, port)) {
+            return false;
+        }
+        if self.allow.is_empty() {
+            return true;
+        }
+        self.allow.iter().any(|rule| rule_matches(rule, host, ip, port))
+    }
+}
+
+/// Match a single `<host-or-cidr>[:<port>]` rule against a target.
+/// `*` matches any host or any port.
+fn rule_matches(rule: &str, host: &str, ip: Option<IpAddr>, port: u16) -> bool {
+    let (pattern, rule_port) = split_rule(rule);
+
+    if let Some(rule_port) = rule_port {
+        if rule_port != "*" && rule_port.parse::<u16>().ok() != Some(port) {
+            return false;
+        }
+    }
+
+    if pattern == "*" {
+        return true;
+    }
+
+    if let Ok(net) = pattern.parse::<IpNet>() {
+        return ip.is_some_and(|ip| net.contains(&ip));
+    }
+
+    if let Ok(rule_ip) = pattern.parse::<IpAddr>() {
+        return ip.is_some_and(|ip| ip == rule_ip);
+    }
+
+    pattern.eq_ignore_ascii_case(host)
+}
+
+/// Split a rule into its host/CIDR pattern and optional port suffix.
+///
+/// A bare `rsplit_once(':')` misparses an unbracketed IPv6 literal (e.g.
+/// `"fe80::1"` splits into pattern `"fe80:"` + port `"1"`, which then never
+/// matches). To avoid that, a port is only split off when: the rule uses
+/// bracketed `[addr]:port` syntax, or the whole rule does *not* already
+/// parse as a bare IP/CIDR on its own.
+fn split_rule(rule: &str) -> (&str, Option<&str>) {
+    if let Some(rest) = rule.strip_prefix('[') {
+        if let Some((addr, after)) = rest.split_once(']') {
+            return (addr, after.strip_prefix(':'));
+        }
+    }
+
+    if rule.parse::<IpNet>().is_ok() || rule.parse::<IpAddr>().is_ok() {
+        return (rule, None);
+    }
+
+    match rule.rsplit_once(':') {
+        Some((pattern, port_str)) if port_str.parse::<u16>().is_ok() || port_str == "*" => {
+            (pattern, Some(port_str))
+        }
+        _ => (rule, None),
+    }
+}
@@ -0,0 +1,99 @@
+//! Shared caching DNS resolver for domain-name targets.
+//!
+//! `tokio::net::lookup_host` delegates to the OS resolver, which doesn't
+//! expose per-record TTLs, so entries could only ever be cached under a
+//! fixed guess. This uses `hickory-resolver` instead, which surfaces the
+//! minimum TTL among the returned records (`Lookup::valid_until`), and
+//! caches each entry for exactly that long so the TCP CONNECT path (ATYP
+//! `0x03`) and the UDP relay's domain branch never serve an address past
+//! its record's real expiry.
+
+use anyhow::{anyhow, Context, Result};
+use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+use hickory_resolver::TokioAsyncResolver;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Entries beyond this count evict the least-recently-used one on insert.
+const MAX_ENTRIES: usize = 4096;
+
+struct CacheEntry {
+    addr: SocketAddr,
+    valid_until: Instant,
+    last_used: Instant,
+}
+
+/// An LRU+TTL cache of `host:port` -> resolved `SocketAddr`, shared across
+/// connections via `Args`.
+pub struct DnsCache {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    resolver: TokioAsyncResolver,
+}
+
+impl std::fmt::Debug for DnsCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DnsCache").finish_non_exhaustive()
+    }
+}
+
+impl Default for DnsCache {
+    fn default() -> Self {
+        // Prefer the system's configured nameservers; fall back to public
+        // resolvers if /etc/resolv.conf can't be read (e.g. in a minimal
+        // container without one).
+        let resolver = TokioAsyncResolver::tokio_from_system_conf()
+            .unwrap_or_else(|_| TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default()));
+        Self { entries: Mutex::new(HashMap::new()), resolver }
+    }
+}
+
+impl DnsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve `host:port`, returning a cached address if still within its
+    /// record's TTL, otherwise resolving via `hickory-resolver` and caching
+    /// the result until its minimum TTL expires.
+    pub async fn resolve(&self, host: &str, port: u16) -> Result<SocketAddr> {
+        let key = format!("{}:{}", host, port);
+
+        if let Some(addr) = self.get_cached(&key) {
+            return Ok(addr);
+        }
+
+        let lookup = self
+            .resolver
+            .lookup_ip(host)
+            .await
+            .with_context(|| format!("DNS lookup failed for {}", host))?;
+        let ip = lookup.iter().next().ok_or_else(|| anyhow!("No addresses found for {}", key))?;
+        let addr = SocketAddr::new(ip, port);
+
+        self.insert(key, addr, lookup.valid_until());
+        Ok(addr)
+    }
+
+    fn get_cached(&self, key: &str) -> Option<SocketAddr> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(key)?;
+        if Instant::now() >= entry.valid_until {
+            entries.remove(key);
+            return None;
+        }
+        entry.last_used = Instant::now();
+        Some(entry.addr)
+    }
+
+    fn insert(&self, key: String, addr: SocketAddr, valid_until: Instant) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(&key) {
+            if let Some(evict_key) = entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone()) {
+                entries.remove(&evict_key);
+            }
+        }
+        entries.insert(key, CacheEntry { addr, valid_until, last_used: Instant::now() });
+    }
+}
@@ -1,13 +1,23 @@
 use anyhow::{Context, Result};
 use clap::Parser;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream, UdpSocket as TokioUdpSocket};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket as TokioUdpSocket, UnixListener};
 use tokio::time::timeout;
 use tracing::{error, info, warn};
 
+mod addr;
+mod config;
+mod dns_cache;
+mod quic;
+mod socks4;
+mod tls;
+mod udp;
+mod upstream;
+
 #[derive(Parser, Debug)]
 #[command(author, version, about = "一个轻量级的 SOCKS5 代理工具", long_about = None)]
 struct Args {
@@ -19,13 +29,73 @@ struct Args {
     #[arg(short, long, default_value = "0.0.0.0")]
     address: String,
 
-    /// 认证用户名 (可选)
-    #[arg(short = 'u', long)]
-    username: Option<String>,
+    /// 多用户配置文件 (TOML)，包含用户名/密码及每用户的访问规则
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// 上游 SOCKS5 代理地址 (host:port)，配置后出站连接将通过该代理转发
+    #[arg(long)]
+    upstream: Option<String>,
+
+    /// 上游代理认证用户名 (可选)
+    #[arg(long)]
+    upstream_username: Option<String>,
+
+    /// 上游代理认证密码 (可选)
+    #[arg(long)]
+    upstream_password: Option<String>,
+
+    /// TLS 证书文件 (PEM)，与 --tls-key 一起使用以启用 socks-over-tls
+    #[arg(long)]
+    tls_cert: Option<PathBuf>,
+
+    /// TLS 私钥文件 (PEM)，与 --tls-cert 一起使用以启用 socks-over-tls
+    #[arg(long)]
+    tls_key: Option<PathBuf>,
+
+    /// 额外监听一个 Unix domain socket，用于同主机进程间的本地代理
+    #[arg(long)]
+    unix_socket: Option<PathBuf>,
 
-    /// 认证密码 (可选)
-    #[arg(short = 'w', long)]
-    password: Option<String>,
+    /// 额外通过 QUIC (quinn) 提供 SOCKS 转发服务的监听地址 (host:port)，
+    /// 需要同时提供 --tls-cert/--tls-key 作为 QUIC 的 TLS 身份
+    #[arg(long)]
+    quic: Option<String>,
+
+    /// 域名解析缓存，供 TCP CONNECT 与 UDP 转发共用 (非命令行参数)
+    #[arg(skip)]
+    dns_cache: Arc<dns_cache::DnsCache>,
+}
+
+/// Placeholder client address used for Unix-domain-socket connections,
+/// which have no network-level peer address of their own.
+fn unix_socket_client_addr() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 0))
+}
+
+/// Build upstream-proxy credentials from `--upstream-username`/`--upstream-password`,
+/// shared by the SOCKS5 and SOCKS4 CONNECT paths.
+fn build_upstream_auth(args: &Args) -> Option<upstream::UpstreamAuth> {
+    args.upstream_username.as_ref().zip(args.upstream_password.as_ref()).map(|(username, password)| {
+        upstream::UpstreamAuth { username: username.clone(), password: password.clone() }
+    })
+}
+
+/// Classify and log a client-handling error the same way regardless of
+/// whether the client connected over TCP or a Unix socket.
+fn log_client_error(label: &str, e: &anyhow::Error) {
+    let msg = e.to_string();
+    // Reduce log level for common scanner/bot errors
+    if msg.contains("Authentication failed")
+        || msg.contains("early eof")
+        || msg.contains("unexpected end of file")
+        || msg.contains("Handshake/Connection timeout")
+        || msg.contains("No supported authentication methods")
+    {
+        warn!("Client warning {}: {}", label, msg);
+    } else {
+        error!("Error handling client {}: {}", label, msg);
+    }
 }
 
 const TIMEOUT_DURATION: Duration = Duration::from_secs(10);
@@ -42,9 +112,65 @@ async fn main() -> Result<()> {
         .await
         .context(format!("Failed to bind to {}", bind_addr))?;
 
+    let server_config = match &args.config {
+        Some(path) => {
+            let loaded = config::Config::load(path)?;
+            info!("Loaded {} user(s) from config {:?}", loaded.users.len(), path);
+            Some(Arc::new(loaded))
+        }
+        None => None,
+    };
+
     info!("SOCKS5 server listening on {}", bind_addr);
-    if args.username.is_some() {
-        info!("Authentication enabled");
+    if let Some(upstream_addr) = &args.upstream {
+        info!("Chaining outbound connections through upstream proxy {}", upstream_addr);
+    }
+
+    let tls_acceptor = match (&args.tls_cert, &args.tls_key) {
+        (Some(cert), Some(key)) => {
+            info!("TLS enabled; wrapping accepted connections with rustls before the SOCKS handshake");
+            Some(tls::build_acceptor(cert, key)?)
+        }
+        (None, None) => None,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "--tls-cert and --tls-key must both be provided to enable socks-over-tls"
+            ));
+        }
+    };
+
+    let unix_listener = match &args.unix_socket {
+        Some(path) => {
+            // Remove a stale socket file left behind by a previous run.
+            let _ = std::fs::remove_file(path);
+            let listener = UnixListener::bind(path)
+                .with_context(|| format!("Failed to bind Unix socket {:?}", path))?;
+            info!("Also listening on Unix socket {:?}", path);
+            Some(listener)
+        }
+        None => None,
+    };
+
+    if let Some(quic_addr) = &args.quic {
+        let (cert, key) = match (&args.tls_cert, &args.tls_key) {
+            (Some(cert), Some(key)) => (cert, key),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "--quic requires --tls-cert and --tls-key (QUIC mandates TLS)"
+                ));
+            }
+        };
+        let quic_tls_config = tls::build_server_config(cert, key)?;
+        let quic_bind: SocketAddr = quic_addr
+            .parse()
+            .with_context(|| format!("Invalid --quic listen address {}", quic_addr))?;
+        let dns_cache = args.dns_cache.clone();
+        info!("Also tunneling SOCKS traffic over QUIC on {}", quic_bind);
+        tokio::spawn(async move {
+            if let Err(e) = quic::run(quic_bind, quic_tls_config, dns_cache).await {
+                error!("QUIC transport error: {}", e);
+            }
+        });
     }
 
     loop {
@@ -65,19 +191,20 @@ async fn main() -> Result<()> {
                         }
 
                         let args = args.clone();
+                        let acceptor = tls_acceptor.clone();
+                        let server_config = server_config.clone();
                         tokio::spawn(async move {
-                            if let Err(e) = handle_client(socket, args).await {
-                                let msg = e.to_string();
-                                // Reduce log level for common scanner/bot errors
-                                if msg.contains("Authentication failed") 
-                                    || msg.contains("early eof") 
-                                    || msg.contains("unexpected end of file")
-                                    || msg.contains("Handshake/Connection timeout") 
-                                    || msg.contains("No supported authentication methods") {
-                                    warn!("Client warning {}: {}", addr, msg);
-                                } else {
-                                    error!("Error handling client {}: {}", addr, e);
+                            let result = if let Some(acceptor) = acceptor {
+                                match acceptor.accept(socket).await {
+                                    Ok(tls_stream) => handle_client(tls_stream, addr, args, server_config).await,
+                                    Err(e) => Err(anyhow::anyhow!("TLS handshake failed: {}", e)),
                                 }
+                            } else {
+                                handle_client(socket, addr, args, server_config).await
+                            };
+
+                            if let Err(e) = result {
+                                log_client_error(&addr.to_string(), &e);
                             }
                         });
                     }
@@ -86,6 +213,36 @@ async fn main() -> Result<()> {
                     }
                 }
             }
+            accept_result = async { unix_listener.as_ref().unwrap().accept().await }, if unix_listener.is_some() => {
+                match accept_result {
+                    Ok((socket, _addr)) => {
+                        info!("Accepted connection on Unix socket {:?}", args.unix_socket.as_ref().unwrap());
+
+                        // No TCP keepalive to set for a Unix domain socket.
+                        let client_addr = unix_socket_client_addr();
+                        let args = args.clone();
+                        let acceptor = tls_acceptor.clone();
+                        let server_config = server_config.clone();
+                        tokio::spawn(async move {
+                            let result = if let Some(acceptor) = acceptor {
+                                match acceptor.accept(socket).await {
+                                    Ok(tls_stream) => handle_client(tls_stream, client_addr, args, server_config).await,
+                                    Err(e) => Err(anyhow::anyhow!("TLS handshake failed: {}", e)),
+                                }
+                            } else {
+                                handle_client(socket, client_addr, args, server_config).await
+                            };
+
+                            if let Err(e) = result {
+                                log_client_error("unix socket client", &e);
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        error!("Failed to accept Unix socket connection: {}", e);
+                    }
+                }
+            }
             _ = tokio::signal::ctrl_c() => {
                 info!("Received shutdown signal, stopping server...");
                 break;
@@ -96,26 +253,60 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn handle_client(mut client_stream: TcpStream, args: Arc<Args>) -> Result<()> {
-    let client_addr = client_stream.peer_addr()?;
+async fn handle_client<S>(
+    mut client_stream: S,
+    client_addr: SocketAddr,
+    args: Arc<Args>,
+    server_config: Option<Arc<config::Config>>,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
     // Wrap the entire handshake and request process in a timeout
     let (mut client_stream, target_stream_opt, udp_associate) = timeout(TIMEOUT_DURATION, async {
         // 1. Handshake
-        let mut buf = [0u8; 2];
-        client_stream.read_exact(&mut buf).await?;
+        let mut ver_buf = [0u8; 1];
+        client_stream.read_exact(&mut ver_buf).await?;
+        let ver = ver_buf[0];
+
+        if ver == 0x04 {
+            // Legacy SOCKS4/SOCKS4a client; handled on a dedicated path and
+            // relayed the same way as a SOCKS5 CONNECT below. SOCKS4 has no
+            // equivalent of SOCKS5's USERNAME/PASSWORD auth, so there's no
+            // authenticated user to check per-user rules against; refuse
+            // outright rather than silently granting unfiltered access when
+            // a multi-user config is active.
+            if server_config.is_some() {
+                client_stream.write_all(&[0x00, 0x5B]).await?;
+                return Err(anyhow::anyhow!(
+                    "Rejected SOCKS4 client: multi-user config requires SOCKS5 USERNAME/PASSWORD auth"
+                ));
+            }
 
-        let ver = buf[0];
-        let nmethods = buf[1];
+            let upstream_auth = build_upstream_auth(&args);
+            let target_stream = socks4::handle_socks4_request(
+                &mut client_stream,
+                &args.dns_cache,
+                args.upstream.as_deref(),
+                upstream_auth.as_ref(),
+            )
+            .await?;
+            return Ok((client_stream, target_stream, None));
+        }
 
         if ver != 0x05 {
             return Err(anyhow::anyhow!("Unsupported SOCKS version: {}", ver));
         }
 
+        let mut nmethods_buf = [0u8; 1];
+        client_stream.read_exact(&mut nmethods_buf).await?;
+        let nmethods = nmethods_buf[0];
+
         let mut methods = vec![0u8; nmethods as usize];
         client_stream.read_exact(&mut methods).await?;
 
         // Authentication Logic
-        if let (Some(username), Some(password)) = (&args.username, &args.password) {
+        let authenticated_user: Option<String> = if let Some(server_config) = &server_config {
             if !methods.contains(&0x02) {
                 client_stream.write_all(&[0x05, 0xFF]).await?;
                 return Err(anyhow::anyhow!("Client does not support USERNAME/PASSWORD auth"));
@@ -141,15 +332,17 @@ async fn handle_client(mut client_stream: TcpStream, args: Arc<Args>) -> Result<
             let mut passwd = vec![0u8; plen[0] as usize];
             client_stream.read_exact(&mut passwd).await?;
 
-            let client_username = String::from_utf8_lossy(&uname);
+            let client_username = String::from_utf8_lossy(&uname).into_owned();
             let client_password = String::from_utf8_lossy(&passwd);
 
-            if client_username != *username || client_password != *password {
+            let matched_user = server_config.find_user(&client_username);
+            if !matched_user.is_some_and(|u| u.password == client_password) {
                 client_stream.write_all(&[0x01, 0x01]).await?; // Auth failed
                 return Err(anyhow::anyhow!("Authentication failed"));
             }
 
             client_stream.write_all(&[0x01, 0x00]).await?; // Auth success
+            Some(client_username)
         } else {
             // No Auth
             if !methods.contains(&0x00) {
@@ -157,7 +350,8 @@ async fn handle_client(mut client_stream: TcpStream, args: Arc<Args>) -> Result<
                 return Err(anyhow::anyhow!("No supported authentication methods"));
             }
             client_stream.write_all(&[0x05, 0x00]).await?;
-        }
+            None
+        };
 
         // 2. Request
         let mut head = [0u8; 4];
@@ -172,54 +366,73 @@ async fn handle_client(mut client_stream: TcpStream, args: Arc<Args>) -> Result<
             return Err(anyhow::anyhow!("Unsupported SOCKS version in request: {}", ver));
         }
 
-        let target_addr_str = match atyp {
-            0x01 => {
-                // IPv4
-                let mut addr_buf = [0u8; 4];
-                client_stream.read_exact(&mut addr_buf).await?;
-                let mut port_buf = [0u8; 2];
-                client_stream.read_exact(&mut port_buf).await?;
-                let port = u16::from_be_bytes(port_buf);
-                format!("{}.{}.{}.{}:{}", addr_buf[0], addr_buf[1], addr_buf[2], addr_buf[3], port)
-            }
-            0x03 => {
-                // Domain name
-                let mut len_buf = [0u8; 1];
-                client_stream.read_exact(&mut len_buf).await?;
-                let len = len_buf[0] as usize;
-                let mut domain_buf = vec![0u8; len];
-                client_stream.read_exact(&mut domain_buf).await?;
-                let domain = String::from_utf8_lossy(&domain_buf);
-                let mut port_buf = [0u8; 2];
-                client_stream.read_exact(&mut port_buf).await?;
-                let port = u16::from_be_bytes(port_buf);
-                format!("{}:{}", domain, port)
-            }
-            0x04 => {
-                // IPv6
-                let mut addr_buf = [0u8; 16];
-                client_stream.read_exact(&mut addr_buf).await?;
-                let mut port_buf = [0u8; 2];
-                client_stream.read_exact(&mut port_buf).await?;
-                let port = u16::from_be_bytes(port_buf);
-                let addr = std::net::Ipv6Addr::from(addr_buf);
-                format!("[{}]:{}", addr, port)
-            }
-            _ => {
-                reply_error(&mut client_stream, 0x08).await?; // Address type not supported
-                return Err(anyhow::anyhow!("Unsupported address type: {}", atyp));
-            }
-        };
+        if !matches!(atyp, 0x01 | 0x03 | 0x04) {
+            reply_error(&mut client_stream, 0x08).await?; // Address type not supported
+            return Err(anyhow::anyhow!("Unsupported address type: {}", atyp));
+        }
+        let parsed_addr = addr::read_addr_port(&mut client_stream, atyp).await?;
+        let target_addr_str = parsed_addr.connect_str;
+        let req_atyp = parsed_addr.atyp;
+        let req_addr_bytes = parsed_addr.addr_bytes;
+        let req_port = parsed_addr.port;
+        let req_host = parsed_addr.host;
+        let req_ip = parsed_addr.ip;
 
         if cmd == 0x01 {
             // CONNECT
+
+            // Per-user access rules (only meaningful when a multi-user
+            // config was loaded; unauthenticated/single-user setups have
+            // none to check).
+            if let (Some(server_config), Some(username)) = (&server_config, &authenticated_user) {
+                if let Some(user) = server_config.find_user(username) {
+                    if !user.is_allowed(&req_host, req_ip, req_port) {
+                        warn!("Denied {} -> {} by ruleset", username, target_addr_str);
+                        reply_error(&mut client_stream, 0x02).await?; // Connection not allowed by ruleset
+                        return Err(anyhow::anyhow!("Connection to {} denied by ruleset for user {}", target_addr_str, username));
+                    }
+                }
+            }
+
             info!("CONNECT target: {}", target_addr_str);
-            let target_stream = match TcpStream::connect(&target_addr_str).await {
-                Ok(stream) => stream,
-                Err(e) => {
-                    error!("Failed to connect to target {}: {}", target_addr_str, e);
-                    reply_error(&mut client_stream, 0x04).await?; // Host unreachable
-                    return Err(e.into());
+            let target_stream = if let Some(upstream_addr) = &args.upstream {
+                let upstream_auth = build_upstream_auth(&args);
+                match upstream::connect_via_upstream(upstream_addr, upstream_auth.as_ref(), req_atyp, &req_addr_bytes, req_port).await {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        error!("Failed to connect via upstream proxy to {}: {}", target_addr_str, e);
+                        reply_error(&mut client_stream, 0x04).await?; // Host unreachable
+                        return Err(e);
+                    }
+                }
+            } else if req_atyp == 0x03 {
+                // Domain name: resolve through the shared caching resolver
+                // instead of letting `TcpStream::connect` hit the system
+                // resolver on every request.
+                let resolved = match args.dns_cache.resolve(&req_host, req_port).await {
+                    Ok(addr) => addr,
+                    Err(e) => {
+                        error!("Failed to resolve target {}: {}", target_addr_str, e);
+                        reply_error(&mut client_stream, 0x04).await?; // Host unreachable
+                        return Err(e);
+                    }
+                };
+                match TcpStream::connect(resolved).await {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        error!("Failed to connect to target {} ({}): {}", target_addr_str, resolved, e);
+                        reply_error(&mut client_stream, 0x04).await?; // Host unreachable
+                        return Err(e.into());
+                    }
+                }
+            } else {
+                match TcpStream::connect(&target_addr_str).await {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        error!("Failed to connect to target {}: {}", target_addr_str, e);
+                        reply_error(&mut client_stream, 0x04).await?; // Host unreachable
+                        return Err(e.into());
+                    }
                 }
             };
 
@@ -231,7 +444,7 @@ async fn handle_client(mut client_stream: TcpStream, args: Arc<Args>) -> Result<
             Ok((client_stream, Some(target_stream), None))
         } else if cmd == 0x03 {
             // UDP ASSOCIATE
-            info!("UDP ASSOCIATE request from {}", client_stream.peer_addr()?);
+            info!("UDP ASSOCIATE request from {}", client_addr);
             
             // Bind a UDP socket on a random port
             let udp_socket = TokioUdpSocket::bind("0.0.0.0:0").await?;
@@ -254,7 +467,7 @@ async fn handle_client(mut client_stream: TcpStream, args: Arc<Args>) -> Result<
 
     if let Some(mut target_stream) = target_stream_opt {
         // TCP Relay
-        let (mut client_reader, mut client_writer) = client_stream.split();
+        let (mut client_reader, mut client_writer) = tokio::io::split(client_stream);
         let (mut target_reader, mut target_writer) = target_stream.split();
 
         let client_to_target = tokio::io::copy(&mut client_reader, &mut target_writer);
@@ -274,7 +487,7 @@ async fn handle_client(mut client_stream: TcpStream, args: Arc<Args>) -> Result<
                 // TCP connection closed or error, close UDP socket
                 info!("TCP control connection closed, stopping UDP associate");
             }
-            res = handle_udp(udp_socket, client_addr) => {
+            res = udp::handle_udp(udp_socket, client_addr, args.dns_cache.clone()) => {
                 res.context("UDP handling failed")?;
             }
         }
@@ -283,100 +496,7 @@ async fn handle_client(mut client_stream: TcpStream, args: Arc<Args>) -> Result<
     Ok(())
 }
 
-async fn handle_udp(socket: TokioUdpSocket, client_addr: SocketAddr) -> Result<()> {
-    let mut buf = vec![0u8; 65535];
-    let header_offset = 300; // Reserve space for header prepending
-    let mut client_udp_addr: Option<SocketAddr> = None;
-    let client_ip = client_addr.ip();
-
-    loop {
-        // Read into buffer with offset
-        let (len, src_addr) = socket.recv_from(&mut buf[header_offset..]).await?;
-        let packet = &buf[header_offset..header_offset + len];
-
-        if src_addr.ip() == client_ip {
-            // Packet from Client -> Target
-            client_udp_addr = Some(src_addr);
-
-            // Parse SOCKS5 UDP Header
-            if len < 3 || packet[0] != 0x00 || packet[1] != 0x00 || packet[2] != 0x00 {
-                continue; // Invalid header or fragmentation
-            }
-
-            let atyp = packet[3];
-            let (target_addr, header_len) = match atyp {
-                0x01 => { // IPv4
-                    if len < 10 { continue; }
-                    let ip = std::net::Ipv4Addr::new(packet[4], packet[5], packet[6], packet[7]);
-                    let port = u16::from_be_bytes([packet[8], packet[9]]);
-                    (SocketAddr::V4(std::net::SocketAddrV4::new(ip, port)), 10)
-                }
-                0x03 => { // Domain
-                    let domain_len = packet[4] as usize;
-                    if len < 5 + domain_len + 2 { continue; }
-                    let domain = String::from_utf8_lossy(&packet[5..5 + domain_len]);
-                    let port = u16::from_be_bytes([packet[5 + domain_len], packet[5 + domain_len + 1]]);
-                    match tokio::net::lookup_host(format!("{}:{}", domain, port)).await {
-                        Ok(mut addrs) => {
-                            if let Some(addr) = addrs.next() {
-                                (addr, 5 + domain_len + 2)
-                            } else { continue; }
-                        }
-                        Err(_) => continue,
-                    }
-                }
-                0x04 => { // IPv6
-                    if len < 22 { continue; }
-                    let ip = std::net::Ipv6Addr::from([
-                        packet[4], packet[5], packet[6], packet[7], packet[8], packet[9], packet[10], packet[11],
-                        packet[12], packet[13], packet[14], packet[15], packet[16], packet[17], packet[18], packet[19]
-                    ]);
-                    let port = u16::from_be_bytes([packet[20], packet[21]]);
-                    (SocketAddr::V6(std::net::SocketAddrV6::new(ip, port, 0, 0)), 22)
-                }
-                _ => continue,
-            };
-
-            let payload = &packet[header_len..];
-            if let Err(e) = socket.send_to(payload, target_addr).await {
-                warn!("Failed to forward UDP packet to {}: {}", target_addr, e);
-            }
-
-        } else {
-            // Packet from Target -> Client
-            if let Some(client_udp) = client_udp_addr {
-                // Prepend SOCKS5 UDP Header
-                let (addr_bytes, port, atyp) = match src_addr {
-                    SocketAddr::V4(a) => (a.ip().octets().to_vec(), a.port(), 0x01),
-                    SocketAddr::V6(a) => (a.ip().octets().to_vec(), a.port(), 0x04),
-                };
-
-                let header_len = 4 + addr_bytes.len() + 2;
-                let start_idx = header_offset - header_len;
-
-                buf[start_idx] = 0x00; // RSV
-                buf[start_idx + 1] = 0x00; // RSV
-                buf[start_idx + 2] = 0x00; // FRAG
-                buf[start_idx + 3] = atyp; // ATYP
-
-                for (i, b) in addr_bytes.iter().enumerate() {
-                    buf[start_idx + 4 + i] = *b;
-                }
-
-                let port_bytes = port.to_be_bytes();
-                buf[start_idx + 4 + addr_bytes.len()] = port_bytes[0];
-                buf[start_idx + 4 + addr_bytes.len() + 1] = port_bytes[1];
-
-                let total_len = header_len + len;
-                if let Err(e) = socket.send_to(&buf[start_idx..start_idx + total_len], client_udp).await {
-                    warn!("Failed to send UDP response to client {}: {}", client_udp, e);
-                }
-            }
-        }
-    }
-}
-
-async fn reply_error(stream: &mut TcpStream, rep: u8) -> Result<()> {
+async fn reply_error<S: AsyncWrite + Unpin>(stream: &mut S, rep: u8) -> Result<()> {
     stream
         .write_all(&[0x05, rep, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
         .await?;
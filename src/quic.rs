@@ -0,0 +1,215 @@
+//! Alternate transport: tunnel SOCKS traffic over QUIC (via quinn) instead
+//! of raw TCP, giving connection migration, 0-RTT reuse, and
+//! head-of-line-blocking-free multiplexing over lossy links.
+//!
+//! Each SOCKS CONNECT maps to a bidirectional QUIC stream and each UDP
+//! ASSOCIATE packet rides a QUIC unreliable datagram; both open with the
+//! same ATYP/addr/port header as the TCP transport (see
+//! `addr::read_addr_port`), modeled on the quinoa forwarder's
+//! `ForwardProtocol::{Tcp, Udp}` split.
+
+use crate::addr;
+use crate::dns_cache::DnsCache;
+use anyhow::{Context, Result};
+use quinn::Endpoint;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio_rustls::rustls;
+use tracing::{error, info, warn};
+
+/// Start a QUIC endpoint on `bind_addr` and serve SOCKS CONNECT/UDP-ASSOCIATE
+/// traffic tunneled over it until the endpoint is closed.
+pub async fn run(bind_addr: SocketAddr, tls_config: Arc<rustls::ServerConfig>, dns_cache: Arc<DnsCache>) -> Result<()> {
+    let quic_crypto = quinn::crypto::rustls::QuicServerConfig::try_from((*tls_config).clone())
+        .context("TLS configuration is not valid for QUIC (requires TLS 1.3)")?;
+    let server_config = quinn::ServerConfig::with_crypto(Arc::new(quic_crypto));
+    let endpoint = Endpoint::server(server_config, bind_addr)
+        .with_context(|| format!("Failed to bind QUIC endpoint on {}", bind_addr))?;
+
+    info!("QUIC transport listening on {}", bind_addr);
+
+    while let Some(connecting) = endpoint.accept().await {
+        let dns_cache = dns_cache.clone();
+        tokio::spawn(async move {
+            match connecting.await {
+                Ok(connection) => {
+                    if let Err(e) = handle_connection(connection, dns_cache).await {
+                        error!("QUIC connection error: {}", e);
+                    }
+                }
+                Err(e) => warn!("QUIC handshake failed: {}", e),
+            }
+        });
+    }
+
+    Ok(())
+}
+
+async fn handle_connection(connection: quinn::Connection, dns_cache: Arc<DnsCache>) -> Result<()> {
+    loop {
+        tokio::select! {
+            stream = connection.accept_bi() => {
+                let (send, recv) = match stream {
+                    Ok(v) => v,
+                    Err(_) => break, // Connection closed
+                };
+                let dns_cache = dns_cache.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_stream(send, recv, dns_cache).await {
+                        warn!("QUIC stream forwarding failed: {}", e);
+                    }
+                });
+            }
+            datagram = connection.read_datagram() => {
+                let datagram = match datagram {
+                    Ok(v) => v,
+                    Err(_) => break, // Connection closed
+                };
+                let connection = connection.clone();
+                let dns_cache = dns_cache.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_datagram(&connection, &datagram, dns_cache).await {
+                        warn!("QUIC datagram forwarding failed: {}", e);
+                    }
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle one multiplexed "SOCKS CONNECT": the stream opener sends the
+/// target ATYP/addr/port header, then bytes are relayed in both directions.
+async fn handle_stream(mut send: quinn::SendStream, mut recv: quinn::RecvStream, dns_cache: Arc<DnsCache>) -> Result<()> {
+    let mut atyp_buf = [0u8; 1];
+    recv.read_exact(&mut atyp_buf).await.context("Failed to read QUIC stream header")?;
+    let parsed = addr::read_addr_port(&mut recv, atyp_buf[0]).await?;
+
+    let connect_result = if parsed.atyp == 0x03 {
+        match dns_cache.resolve(&parsed.host, parsed.port).await {
+            Ok(resolved) => TcpStream::connect(resolved).await,
+            Err(e) => {
+                send.finish().ok();
+                return Err(e);
+            }
+        }
+    } else {
+        TcpStream::connect(&parsed.connect_str).await
+    };
+
+    let mut target_stream = match connect_result {
+        Ok(stream) => stream,
+        Err(e) => {
+            send.finish().ok();
+            return Err(e.into());
+        }
+    };
+
+    info!("QUIC CONNECT target: {}", parsed.connect_str);
+
+    let (mut target_reader, mut target_writer) = target_stream.split();
+    let client_to_target = tokio::io::copy(&mut recv, &mut target_writer);
+    let target_to_client = tokio::io::copy(&mut target_reader, &mut send);
+
+    tokio::select! {
+        res = client_to_target => { res.context("QUIC stream to target failed")?; }
+        res = target_to_client => { res.context("Target to QUIC stream failed")?; }
+    }
+
+    Ok(())
+}
+
+struct DatagramHeader {
+    header_len: usize,
+    host: String,
+    ip: Option<std::net::IpAddr>,
+    port: u16,
+}
+
+/// Parse a `ATYP addr PORT` header from a QUIC datagram, mirroring
+/// `addr::read_addr_port` but over an already-received byte slice instead
+/// of an async reader.
+fn parse_datagram_header(buf: &[u8]) -> Option<DatagramHeader> {
+    let atyp = *buf.first()?;
+    match atyp {
+        0x01 => {
+            if buf.len() < 7 {
+                return None;
+            }
+            let ip = std::net::Ipv4Addr::new(buf[1], buf[2], buf[3], buf[4]);
+            let port = u16::from_be_bytes([buf[5], buf[6]]);
+            Some(DatagramHeader { header_len: 7, host: ip.to_string(), ip: Some(ip.into()), port })
+        }
+        0x03 => {
+            let len = *buf.get(1)? as usize;
+            if buf.len() < 2 + len + 2 {
+                return None;
+            }
+            let domain = String::from_utf8_lossy(&buf[2..2 + len]).into_owned();
+            let port = u16::from_be_bytes([buf[2 + len], buf[2 + len + 1]]);
+            Some(DatagramHeader { header_len: 2 + len + 2, host: domain, ip: None, port })
+        }
+        0x04 => {
+            if buf.len() < 19 {
+                return None;
+            }
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&buf[1..17]);
+            let ip = std::net::Ipv6Addr::from(octets);
+            let port = u16::from_be_bytes([buf[17], buf[18]]);
+            Some(DatagramHeader { header_len: 19, host: ip.to_string(), ip: Some(ip.into()), port })
+        }
+        _ => None,
+    }
+}
+
+/// Handle one UDP-ASSOCIATE datagram: header+payload in, a single reply
+/// datagram with the same header format out. Unlike the persistent
+/// per-flow sockets used for the regular UDP relay (`udp`), each QUIC
+/// datagram is forwarded independently since QUIC datagrams are
+/// themselves unordered and unreliable.
+async fn handle_datagram(connection: &quinn::Connection, datagram: &[u8], dns_cache: Arc<DnsCache>) -> Result<()> {
+    let Some(header) = parse_datagram_header(datagram) else {
+        return Ok(());
+    };
+    let payload = &datagram[header.header_len..];
+
+    let target_addr = match header.ip {
+        Some(ip) => SocketAddr::from((ip, header.port)),
+        None => dns_cache.resolve(&header.host, header.port).await?,
+    };
+
+    let bind_addr = match target_addr {
+        SocketAddr::V4(_) => "0.0.0.0:0",
+        SocketAddr::V6(_) => "[::]:0",
+    };
+    let socket = UdpSocket::bind(bind_addr).await?;
+    socket.send_to(payload, target_addr).await?;
+
+    let mut reply_buf = vec![0u8; 65535];
+    let (len, from) = tokio::time::timeout(Duration::from_secs(10), socket.recv_from(&mut reply_buf))
+        .await
+        .context("UDP reply timed out")??;
+
+    let mut out = Vec::with_capacity(len + 19);
+    match from {
+        SocketAddr::V4(a) => {
+            out.push(0x01);
+            out.extend_from_slice(&a.ip().octets());
+            out.extend_from_slice(&a.port().to_be_bytes());
+        }
+        SocketAddr::V6(a) => {
+            out.push(0x04);
+            out.extend_from_slice(&a.ip().octets());
+            out.extend_from_slice(&a.port().to_be_bytes());
+        }
+    }
+    out.extend_from_slice(&reply_buf[..len]);
+
+    connection.send_datagram(out.into())?;
+    Ok(())
+}
@@ -0,0 +1,143 @@
+//! SOCKS4 / SOCKS4a request handling.
+//!
+//! This is a legacy fallback path used only when a client's first byte is
+//! `0x04` instead of the SOCKS5 `0x05`. It has no method negotiation or
+//! authentication phase: the request is read and answered directly.
+//!
+//! Outbound connections reuse the same `--upstream` chaining and shared
+//! `dns_cache` as the SOCKS5 CONNECT path, so a configured upstream proxy
+//! sees all egress and a SOCKS4a client gets the same caching behavior as
+//! SOCKS5/QUIC clients instead of an uncached per-request OS resolver call.
+
+use crate::dns_cache::DnsCache;
+use crate::upstream::{self, UpstreamAuth};
+use anyhow::{anyhow, Result};
+use std::net::{Ipv4Addr, SocketAddr};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{error, info};
+
+const SOCKS4_GRANTED: u8 = 0x5A;
+const SOCKS4_REJECTED: u8 = 0x5B;
+
+/// Handle a SOCKS4/SOCKS4a request, assuming the leading `VER(0x04)` byte
+/// has already been consumed by the caller.
+///
+/// On success returns the connected target stream. On rejection (bad
+/// command or failed resolution/connect) a rejection reply has already
+/// been written to `client_stream` and an error is returned.
+pub async fn handle_socks4_request<S: AsyncRead + AsyncWrite + Unpin>(
+    client_stream: &mut S,
+    dns_cache: &DnsCache,
+    upstream_addr: Option<&str>,
+    upstream_auth: Option<&UpstreamAuth>,
+) -> Result<Option<TcpStream>> {
+    // CMD(1) DSTPORT(2) DSTIP(4)
+    let mut head = [0u8; 7];
+    client_stream.read_exact(&mut head).await?;
+
+    let cmd = head[0];
+    let port = u16::from_be_bytes([head[1], head[2]]);
+    let dst_ip = Ipv4Addr::new(head[3], head[4], head[5], head[6]);
+
+    // USERID: null-terminated, ignored (no identd-based auth here).
+    read_until_null(client_stream).await?;
+
+    if cmd != 0x01 {
+        // Reject before doing any SOCKS4a hostname resolution below; there's
+        // no point looking up a host for a command we don't support.
+        reply(client_stream, SOCKS4_REJECTED, dst_ip, port).await?;
+        return Err(anyhow!("Unsupported SOCKS4 command: {}", cmd));
+    }
+
+    // SOCKS4a: a DSTIP of the form 0.0.0.x (x != 0) means a null-terminated
+    // hostname follows the userid and must be resolved by the proxy.
+    let octets = dst_ip.octets();
+    let hostname = if octets[0] == 0 && octets[1] == 0 && octets[2] == 0 && octets[3] != 0 {
+        let hostname_bytes = read_until_null(client_stream).await?;
+        Some(String::from_utf8_lossy(&hostname_bytes).into_owned())
+    } else {
+        None
+    };
+
+    let target_label = hostname.clone().unwrap_or_else(|| dst_ip.to_string());
+
+    let connect_result: Result<TcpStream> = if let Some(upstream_addr) = upstream_addr {
+        // Forward the original ATYP/addr verbatim (domain name if this was
+        // SOCKS4a) rather than pre-resolving, so hostname resolution happens
+        // at the upstream hop like a SOCKS5 CONNECT would.
+        let (atyp, addr_bytes) = match &hostname {
+            Some(host) => (0x03u8, domain_addr_bytes(host)),
+            None => (0x01u8, octets.to_vec()),
+        };
+        info!("SOCKS4 CONNECT target (via upstream {}): {}:{}", upstream_addr, target_label, port);
+        upstream::connect_via_upstream(upstream_addr, upstream_auth, atyp, &addr_bytes, port).await
+    } else {
+        let target_addr = match &hostname {
+            Some(host) => match dns_cache.resolve(host, port).await {
+                Ok(addr) => addr,
+                Err(e) => {
+                    reply(client_stream, SOCKS4_REJECTED, dst_ip, port).await?;
+                    return Err(anyhow!("Failed to resolve SOCKS4a host {}: {}", host, e));
+                }
+            },
+            None => SocketAddr::from((dst_ip, port)),
+        };
+        info!("SOCKS4 CONNECT target: {}", target_addr);
+        TcpStream::connect(target_addr).await.map_err(Into::into)
+    };
+
+    match connect_result {
+        Ok(target_stream) => {
+            reply(client_stream, SOCKS4_GRANTED, dst_ip, port).await?;
+            Ok(Some(target_stream))
+        }
+        Err(e) => {
+            error!("Failed to connect to SOCKS4 target {}:{}: {}", target_label, port, e);
+            reply(client_stream, SOCKS4_REJECTED, dst_ip, port).await?;
+            Err(e)
+        }
+    }
+}
+
+/// Build the length-prefixed domain-name ATYP payload (`LEN domain`) SOCKS5
+/// uses on the wire, for forwarding a SOCKS4a hostname through
+/// `upstream::connect_via_upstream`.
+fn domain_addr_bytes(host: &str) -> Vec<u8> {
+    let mut bytes = vec![host.len() as u8];
+    bytes.extend_from_slice(host.as_bytes());
+    bytes
+}
+
+async fn read_until_null<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte).await?;
+        if byte[0] == 0 {
+            break;
+        }
+        out.push(byte[0]);
+    }
+    Ok(out)
+}
+
+async fn reply<S: AsyncWrite + Unpin>(stream: &mut S, code: u8, dst_ip: Ipv4Addr, port: u16) -> Result<()> {
+    // Echo the request's DSTPORT/DSTIP as the spec requires; lenient clients
+    // ignore these fields but strict ones validate them.
+    let port_bytes = port.to_be_bytes();
+    let ip_octets = dst_ip.octets();
+    stream
+        .write_all(&[
+            0x00,
+            code,
+            port_bytes[0],
+            port_bytes[1],
+            ip_octets[0],
+            ip_octets[1],
+            ip_octets[2],
+            ip_octets[3],
+        ])
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,51 @@
+//! TLS wrapping for the SOCKS5 control channel ("socks-over-tls").
+//!
+//! When `--tls-cert`/`--tls-key` are supplied, every accepted `TcpStream` is
+//! wrapped with a rustls `ServerConfig` via tokio-rustls before the SOCKS
+//! handshake begins, so the whole negotiation and relay run encrypted.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+
+/// Load a certificate chain and private key from PEM files and build a
+/// `TlsAcceptor` ready to wrap accepted sockets.
+pub fn build_acceptor(cert_path: &Path, key_path: &Path) -> Result<TlsAcceptor> {
+    Ok(TlsAcceptor::from(build_server_config(cert_path, key_path)?))
+}
+
+/// Load a certificate chain and private key from PEM files and build a
+/// rustls `ServerConfig`, shared by the tokio-rustls TCP acceptor and the
+/// QUIC transport (`quic`), which both need the same TLS identity.
+pub fn build_server_config(cert_path: &Path, key_path: &Path) -> Result<Arc<ServerConfig>> {
+    let certs = load_certs(cert_path)?;
+    let key = load_private_key(key_path)?;
+
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("Failed to build TLS server configuration")?;
+
+    Ok(Arc::new(config))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let file = File::open(path).with_context(|| format!("Failed to open TLS cert file {:?}", path))?;
+    let mut reader = BufReader::new(file);
+    rustls_pemfile::certs(&mut reader)
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Failed to parse TLS certificates from {:?}", path))
+}
+
+fn load_private_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    let file = File::open(path).with_context(|| format!("Failed to open TLS key file {:?}", path))?;
+    let mut reader = BufReader::new(file);
+    rustls_pemfile::private_key(&mut reader)
+        .with_context(|| format!("Failed to parse TLS private key from {:?}", path))?
+        .ok_or_else(|| anyhow::anyhow!("No private key found in {:?}", path))
+}
@@ -0,0 +1,222 @@
+//! Per-association UDP relaying for SOCKS5 UDP ASSOCIATE.
+//!
+//! Each association already gets its own client-facing socket (bound by
+//! the caller per TCP control connection); this module additionally gives
+//! each `(client endpoint, target)` pair its own outbound socket instead of
+//! funneling everything through one socket and guessing packet direction
+//! from the source IP, which misroutes when a client and a target share an
+//! IP. The client's relay endpoint is latched from its first packet rather
+//! than just its IP, and idle flows are reaped after `FLOW_IDLE_TIMEOUT`.
+
+use crate::dns_cache::DnsCache;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+const FLOW_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+const REAP_INTERVAL: Duration = Duration::from_secs(30);
+
+type FlowKey = (SocketAddr, SocketAddr);
+
+struct Flow {
+    socket: Arc<UdpSocket>,
+    // Shared with the flow's `forward_target_to_client` task so that
+    // inbound (target -> client) traffic also counts as activity, not just
+    // the client -> target direction updated in `get_or_create_flow`.
+    last_active: Arc<Mutex<Instant>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+pub async fn handle_udp(client_socket: UdpSocket, client_addr: SocketAddr, dns_cache: Arc<DnsCache>) -> Result<()> {
+    let client_socket = Arc::new(client_socket);
+    let client_ip = client_addr.ip();
+    let mut expected_client_endpoint: Option<SocketAddr> = None;
+    let flows: Arc<Mutex<HashMap<FlowKey, Flow>>> = Arc::new(Mutex::new(HashMap::new()));
+
+    let mut reap_interval = tokio::time::interval(REAP_INTERVAL);
+    let mut buf = vec![0u8; 65535];
+
+    loop {
+        tokio::select! {
+            recv = client_socket.recv_from(&mut buf) => {
+                let (len, src_addr) = recv?;
+
+                if src_addr.ip() != client_ip {
+                    continue;
+                }
+                // Latch the client's declared relay endpoint from its first
+                // packet rather than just its IP, so a client and a target
+                // sharing an IP (e.g. localhost testing) can't be confused.
+                let client_endpoint = *expected_client_endpoint.get_or_insert(src_addr);
+                if src_addr != client_endpoint {
+                    continue;
+                }
+
+                let packet = &buf[..len];
+                if len < 4 || packet[0] != 0x00 || packet[1] != 0x00 || packet[2] != 0x00 {
+                    continue; // Invalid header or fragmentation
+                }
+
+                let atyp = packet[3];
+                let Some((target_addr, header_len)) = parse_target(atyp, packet, &dns_cache).await else {
+                    continue;
+                };
+                let payload = packet[header_len..].to_vec();
+
+                let flow_socket = match get_or_create_flow(&flows, &client_socket, client_endpoint, target_addr).await {
+                    Ok(socket) => socket,
+                    Err(e) => {
+                        warn!("Failed to create UDP flow to {}: {}", target_addr, e);
+                        continue;
+                    }
+                };
+                if let Err(e) = flow_socket.send_to(&payload, target_addr).await {
+                    warn!("Failed to forward UDP packet to {}: {}", target_addr, e);
+                }
+            }
+            _ = reap_interval.tick() => {
+                reap_idle_flows(&flows).await;
+            }
+        }
+    }
+}
+
+async fn parse_target(atyp: u8, packet: &[u8], dns_cache: &DnsCache) -> Option<(SocketAddr, usize)> {
+    match atyp {
+        0x01 => {
+            if packet.len() < 10 {
+                return None;
+            }
+            let ip = Ipv4Addr::new(packet[4], packet[5], packet[6], packet[7]);
+            let port = u16::from_be_bytes([packet[8], packet[9]]);
+            Some((SocketAddr::from((ip, port)), 10))
+        }
+        0x03 => {
+            let domain_len = *packet.get(4)? as usize;
+            if packet.len() < 5 + domain_len + 2 {
+                return None;
+            }
+            let domain = String::from_utf8_lossy(&packet[5..5 + domain_len]).into_owned();
+            let port = u16::from_be_bytes([packet[5 + domain_len], packet[5 + domain_len + 1]]);
+            let addr = dns_cache.resolve(&domain, port).await.ok()?;
+            Some((addr, 5 + domain_len + 2))
+        }
+        0x04 => {
+            if packet.len() < 22 {
+                return None;
+            }
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&packet[4..20]);
+            let ip = Ipv6Addr::from(octets);
+            let port = u16::from_be_bytes([packet[20], packet[21]]);
+            Some((SocketAddr::from((ip, port)), 22))
+        }
+        _ => None,
+    }
+}
+
+async fn get_or_create_flow(
+    flows: &Arc<Mutex<HashMap<FlowKey, Flow>>>,
+    client_socket: &Arc<UdpSocket>,
+    client_endpoint: SocketAddr,
+    target_addr: SocketAddr,
+) -> Result<Arc<UdpSocket>> {
+    let key = (client_endpoint, target_addr);
+
+    let mut flows_guard = flows.lock().await;
+    if let Some(flow) = flows_guard.get_mut(&key) {
+        *flow.last_active.lock().await = Instant::now();
+        return Ok(flow.socket.clone());
+    }
+
+    let bind_addr = match target_addr {
+        SocketAddr::V4(_) => "0.0.0.0:0",
+        SocketAddr::V6(_) => "[::]:0",
+    };
+    let outbound = Arc::new(UdpSocket::bind(bind_addr).await?);
+    let last_active = Arc::new(Mutex::new(Instant::now()));
+
+    let task = tokio::spawn(forward_target_to_client(
+        outbound.clone(),
+        client_socket.clone(),
+        client_endpoint,
+        target_addr,
+        last_active.clone(),
+    ));
+
+    flows_guard.insert(key, Flow { socket: outbound.clone(), last_active, task });
+    Ok(outbound)
+}
+
+/// Relay packets from a single target back to the client, prepending the
+/// SOCKS5 UDP header.
+async fn forward_target_to_client(
+    outbound: Arc<UdpSocket>,
+    client_socket: Arc<UdpSocket>,
+    client_endpoint: SocketAddr,
+    target_addr: SocketAddr,
+    last_active: Arc<Mutex<Instant>>,
+) {
+    let mut buf = vec![0u8; 65535];
+    let header_offset = 300; // Reserve space for header prepending
+
+    loop {
+        let (len, from) = match outbound.recv_from(&mut buf[header_offset..]).await {
+            Ok(v) => v,
+            Err(_) => break,
+        };
+        if from != target_addr {
+            continue; // Drop packets from anyone but this flow's target
+        }
+        *last_active.lock().await = Instant::now();
+
+        let (addr_bytes, port, atyp) = match from {
+            SocketAddr::V4(a) => (a.ip().octets().to_vec(), a.port(), 0x01u8),
+            SocketAddr::V6(a) => (a.ip().octets().to_vec(), a.port(), 0x04u8),
+        };
+
+        let header_len = 4 + addr_bytes.len() + 2;
+        let start_idx = header_offset - header_len;
+
+        buf[start_idx] = 0x00; // RSV
+        buf[start_idx + 1] = 0x00; // RSV
+        buf[start_idx + 2] = 0x00; // FRAG
+        buf[start_idx + 3] = atyp; // ATYP
+
+        for (i, b) in addr_bytes.iter().enumerate() {
+            buf[start_idx + 4 + i] = *b;
+        }
+
+        let port_bytes = port.to_be_bytes();
+        buf[start_idx + 4 + addr_bytes.len()] = port_bytes[0];
+        buf[start_idx + 4 + addr_bytes.len() + 1] = port_bytes[1];
+
+        let total_len = header_len + len;
+        if let Err(e) = client_socket.send_to(&buf[start_idx..start_idx + total_len], client_endpoint).await {
+            warn!("Failed to send UDP response to client {}: {}", client_endpoint, e);
+        }
+    }
+}
+
+async fn reap_idle_flows(flows: &Arc<Mutex<HashMap<FlowKey, Flow>>>) {
+    let mut flows_guard = flows.lock().await;
+    let now = Instant::now();
+    let mut dead = Vec::new();
+    for (key, flow) in flows_guard.iter() {
+        if now.duration_since(*flow.last_active.lock().await) >= FLOW_IDLE_TIMEOUT {
+            dead.push(*key);
+        }
+    }
+    for key in dead {
+        if let Some(flow) = flows_guard.remove(&key) {
+            flow.task.abort();
+            info!("Reaped idle UDP flow {} -> {}", key.0, key.1);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Client-side SOCKS5 handshake used to chain this proxy's outbound
+//! connections through another SOCKS5 server ("proxy cascading").
+
+use anyhow::{anyhow, Result};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Credentials for authenticating to the upstream SOCKS5 proxy.
+#[derive(Debug, Clone)]
+pub struct UpstreamAuth {
+    pub username: String,
+    pub password: String,
+}
+
+/// Connect to `upstream_addr` and perform a SOCKS5 CONNECT for the target
+/// described by `atyp`/`addr`/`port` (as parsed from the original client
+/// request), returning the resulting stream to relay against.
+pub async fn connect_via_upstream(
+    upstream_addr: &str,
+    auth: Option<&UpstreamAuth>,
+    atyp: u8,
+    addr: &[u8],
+    port: u16,
+) -> Result<TcpStream> {
+    let mut stream = TcpStream::connect(upstream_addr).await?;
+
+    // 1. Method negotiation
+    let methods: &[u8] = if auth.is_some() { &[0x00, 0x02] } else { &[0x00] };
+    let mut hello = vec![0x05, methods.len() as u8];
+    hello.extend_from_slice(methods);
+    stream.write_all(&hello).await?;
+
+    let mut method_reply = [0u8; 2];
+    stream.read_exact(&mut method_reply).await?;
+    if method_reply[0] != 0x05 {
+        return Err(anyhow!(
+            "Upstream proxy returned unexpected SOCKS version: {}",
+            method_reply[0]
+        ));
+    }
+
+    match method_reply[1] {
+        0x00 => {}
+        0x02 => {
+            let auth = auth.ok_or_else(|| {
+                anyhow!("Upstream proxy requires USERNAME/PASSWORD auth but no upstream credentials were configured")
+            })?;
+            let mut req = vec![0x01, auth.username.len() as u8];
+            req.extend_from_slice(auth.username.as_bytes());
+            req.push(auth.password.len() as u8);
+            req.extend_from_slice(auth.password.as_bytes());
+            stream.write_all(&req).await?;
+
+            let mut auth_reply = [0u8; 2];
+            stream.read_exact(&mut auth_reply).await?;
+            if auth_reply[1] != 0x00 {
+                return Err(anyhow!("Upstream proxy rejected USERNAME/PASSWORD authentication"));
+            }
+        }
+        0xFF => return Err(anyhow!("Upstream proxy rejected all offered authentication methods")),
+        m => return Err(anyhow!("Upstream proxy selected unsupported auth method: {}", m)),
+    }
+
+    // 2. CONNECT request, carrying the original ATYP/addr/port verbatim.
+    let mut req = vec![0x05, 0x01, 0x00, atyp];
+    req.extend_from_slice(addr);
+    req.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&req).await?;
+
+    // 3. Reply: VER REP RSV ATYP BND.ADDR BND.PORT
+    let mut head = [0u8; 4];
+    stream.read_exact(&mut head).await?;
+    if head[0] != 0x05 {
+        return Err(anyhow!("Upstream proxy sent unexpected reply version: {}", head[0]));
+    }
+    if head[1] != 0x00 {
+        return Err(anyhow!(
+            "Upstream proxy CONNECT failed with reply code: {}",
+            head[1]
+        ));
+    }
+
+    let bnd_addr_len = match head[3] {
+        0x01 => 4,
+        0x03 => {
+            let mut len_buf = [0u8; 1];
+            stream.read_exact(&mut len_buf).await?;
+            len_buf[0] as usize
+        }
+        0x04 => 16,
+        a => return Err(anyhow!("Upstream proxy returned unsupported BND.ADDR type: {}", a)),
+    };
+    let mut bnd_rest = vec![0u8; bnd_addr_len + 2]; // + BND.PORT
+    stream.read_exact(&mut bnd_rest).await?;
+
+    Ok(stream)
+}